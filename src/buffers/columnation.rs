@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use timely::container::columnation::{Columnation, TimelyStack};
+use timely::progress::Timestamp;
+use timely::Data;
+
+use crate::generic::WindowBuffer;
+
+/// Up to this many entries in the region (live and dead together),
+/// compaction isn't worth the rebuild.
+const COMPACT_MIN_LEN: usize = 1024;
+
+/// A `WindowBuffer` that stores `(time, datum)` pairs contiguously in a
+/// single `TimelyStack` arena rather than one `Vec` per timestamp, so wide
+/// windows holding millions of small records don't fragment the allocator.
+/// An index from timestamp to the arena offsets it owns keeps `timestamps`/
+/// `remove` working the same as the `HashMap` buffer.
+#[cfg(feature = "columnation")]
+pub struct ColumnationBuffer<T: Timestamp + Columnation, D: Data + Columnation> {
+    region: TimelyStack<(T, D)>,
+    index: HashMap<T, Vec<usize>>,
+    /// Entries in `region` still referenced by `index`; the rest is dead
+    /// space left behind by `remove`.
+    live: usize,
+}
+
+#[cfg(feature = "columnation")]
+impl<T: Timestamp + Columnation, D: Data + Columnation> Default for ColumnationBuffer<T, D> {
+    fn default() -> Self {
+        Self {
+            region: TimelyStack::default(),
+            index: HashMap::default(),
+            live: 0,
+        }
+    }
+}
+
+#[cfg(feature = "columnation")]
+impl<T: Timestamp + Columnation, D: Data + Columnation> ColumnationBuffer<T, D> {
+    /// Rebuild `region` with only the live entries once dead space crosses
+    /// half the region, reclaiming the space left behind by `remove`:
+    /// copy every still-referenced entry into a fresh arena and remap
+    /// `index` to the new positions.
+    fn maybe_compact(&mut self) {
+        if self.region.len() <= COMPACT_MIN_LEN || self.live * 2 >= self.region.len() {
+            return;
+        }
+
+        let mut region = TimelyStack::default();
+        for positions in self.index.values_mut() {
+            for pos in positions.iter_mut() {
+                let new_pos = region.len();
+                region.copy(&self.region[*pos]);
+                *pos = new_pos;
+            }
+        }
+        self.region = region;
+    }
+}
+
+#[cfg(feature = "columnation")]
+impl<T: Timestamp + Columnation, D: Data + Columnation> WindowBuffer for ColumnationBuffer<T, D> {
+    type Timestamp = T;
+    type Datum = D;
+
+    fn timestamps(&self) -> Vec<&Self::Timestamp> {
+        self.index.keys().collect::<Vec<_>>()
+    }
+
+    fn store(&mut self, time: Self::Timestamp, data: Vec<Self::Datum>) {
+        let positions = self.index.entry(time.clone()).or_default();
+        for datum in data {
+            positions.push(self.region.len());
+            self.region.copy(&(time.clone(), datum));
+            self.live += 1;
+        }
+    }
+
+    fn get(&self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        self.index
+            .get(time)
+            .map(|positions| positions.iter().map(|&i| self.region[i].1.clone()).collect())
+    }
+
+    fn remove(&mut self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        let data = self.get(time);
+        if let Some(positions) = self.index.remove(time) {
+            self.live -= positions.len();
+        }
+        self.maybe_compact();
+        data
+    }
+}
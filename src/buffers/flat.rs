@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use flatcontainer::{FlatStack, Region};
+use timely::progress::Timestamp;
+use timely::Data;
+
+use crate::generic::WindowBuffer;
+
+/// Up to this many entries in the region (live and dead together),
+/// compaction isn't worth the rebuild.
+const COMPACT_MIN_LEN: usize = 1024;
+
+/// A `WindowBuffer` built on `flatcontainer::FlatStack`, the same
+/// arena-per-region idea as `ColumnationBuffer` but using flatcontainer's
+/// region abstraction instead of `Columnation`. `R` is the flat region that
+/// stores `(time, datum)` pairs; an index from timestamp to region indices
+/// gives `timestamps`/`remove` the usual `WindowBuffer` contract.
+#[cfg(feature = "flatcontainer")]
+pub struct FlatStackBuffer<T, D, R>
+where
+    T: Timestamp,
+    D: Data,
+    R: Region<Owned = (T, D)> + Default,
+{
+    region: FlatStack<R>,
+    index: HashMap<T, Vec<usize>>,
+    /// Entries in `region` still referenced by `index`; the rest is dead
+    /// space left behind by `remove`.
+    live: usize,
+}
+
+#[cfg(feature = "flatcontainer")]
+impl<T, D, R> Default for FlatStackBuffer<T, D, R>
+where
+    T: Timestamp,
+    D: Data,
+    R: Region<Owned = (T, D)> + Default,
+{
+    fn default() -> Self {
+        Self {
+            region: FlatStack::default(),
+            index: HashMap::default(),
+            live: 0,
+        }
+    }
+}
+
+#[cfg(feature = "flatcontainer")]
+impl<T, D, R> FlatStackBuffer<T, D, R>
+where
+    T: Timestamp,
+    D: Data,
+    R: Region<Owned = (T, D)> + Default,
+{
+    /// Rebuild `region` with only the live entries once dead space crosses
+    /// half the region, reclaiming the space left behind by `remove`:
+    /// copy every still-referenced entry into a fresh region and remap
+    /// `index` to the new positions.
+    fn maybe_compact(&mut self) {
+        if self.region.len() <= COMPACT_MIN_LEN || self.live * 2 >= self.region.len() {
+            return;
+        }
+
+        let mut region = FlatStack::default();
+        for positions in self.index.values_mut() {
+            for pos in positions.iter_mut() {
+                let new_pos = region.len();
+                let item: (T, D) = self.region.get(*pos).into();
+                region.copy(item);
+                *pos = new_pos;
+            }
+        }
+        self.region = region;
+    }
+}
+
+#[cfg(feature = "flatcontainer")]
+impl<T, D, R> WindowBuffer for FlatStackBuffer<T, D, R>
+where
+    T: Timestamp,
+    D: Data,
+    R: Region<Owned = (T, D)> + Default,
+{
+    type Timestamp = T;
+    type Datum = D;
+
+    fn timestamps(&self) -> Vec<&Self::Timestamp> {
+        self.index.keys().collect::<Vec<_>>()
+    }
+
+    fn store(&mut self, time: Self::Timestamp, data: Vec<Self::Datum>) {
+        let positions = self.index.entry(time.clone()).or_default();
+        for datum in data {
+            positions.push(self.region.len());
+            self.region.copy((time.clone(), datum));
+            self.live += 1;
+        }
+    }
+
+    fn get(&self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        self.index.get(time).map(|positions| {
+            positions
+                .iter()
+                .map(|&i| {
+                    let (_, datum): (T, D) = self.region.get(i).into();
+                    datum
+                })
+                .collect()
+        })
+    }
+
+    fn remove(&mut self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        let data = self.get(time);
+        if let Some(positions) = self.index.remove(time) {
+            self.live -= positions.len();
+        }
+        self.maybe_compact();
+        data
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
 use timely::dataflow::channels::pact::Pipeline;
-use timely::dataflow::operators::{FrontierNotificator, Operator};
+use timely::dataflow::operators::probe::Handle as ProbeHandle;
+use timely::dataflow::operators::{Capability, FrontierNotificator, Operator};
 use timely::dataflow::{Scope, Stream};
+use timely::order::PartialOrder;
 use timely::progress::frontier::MutableAntichain;
-use timely::progress::Timestamp;
+use timely::progress::{PathSummary, Timestamp};
 use timely::Data;
 
 pub trait WindowBuffer: Default {
@@ -17,6 +19,13 @@ pub trait WindowBuffer: Default {
     /// Store data with timestamp in buffer
     fn store(&mut self, time: Self::Timestamp, data: Vec<Self::Datum>);
 
+    /// Peek the data buffered for a timestamp without removing it, for
+    /// windows that may need to read the same record more than once (e.g.
+    /// overlapping sliding windows). Returns owned data rather than a
+    /// borrow, since arena-backed implementations can't hand back a
+    /// reference into their storage.
+    fn get(&self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>>;
+
     /// Remove buffered timestamp and pop its data
     fn remove(&mut self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>>;
 }
@@ -33,6 +42,10 @@ impl<T: Timestamp, D: Data> WindowBuffer for HashMap<T, Vec<D>> {
         self.entry(time).or_default().extend(data);
     }
 
+    fn get(&self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        HashMap::<T, Vec<D>>::get(self, time).cloned()
+    }
+
     fn remove(&mut self, time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
         HashMap::<T, Vec<D>>::remove(self, time)
     }
@@ -56,12 +69,34 @@ impl<'w, T: Timestamp> Watermark<'w, T> {
     }
 }
 
-pub type EmitResult<B> = Option<(
+/// Which kind of pane a window emission represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Firing {
+    /// A speculative pane fired before the window has closed.
+    Early,
+    /// The regular pane fired once the watermark passes the window's end.
+    OnTime,
+    /// A pane re-fired with late-arriving data, within `allowed_lateness`
+    /// of the window's end.
+    Late,
+}
+
+/// What a window emits for a given timestamp: the window's own time, the
+/// kind of firing it represents, and the `(source_time, value)` entries
+/// that make it up. `O` is the emitted value type, which may differ from
+/// the buffer's `Datum` (e.g. an aggregate rather than the raw record).
+pub type EmitResult<B, O> = Option<(
     <B as WindowBuffer>::Timestamp,
-    Vec<(<B as WindowBuffer>::Timestamp, <B as WindowBuffer>::Datum)>,
+    Firing,
+    Vec<(<B as WindowBuffer>::Timestamp, O)>,
 )>;
 
 pub trait Window<B: WindowBuffer> {
+    /// The value emitted per window. Implementations that replay raw data
+    /// set this to `B::Datum`; implementations that aggregate set it to
+    /// their accumulator type.
+    type Output: Data;
+
     /// Get buffer reference
     fn buffer(&mut self) -> &mut B;
 
@@ -87,25 +122,126 @@ pub trait Window<B: WindowBuffer> {
     /// The hook which will be invoked when given new data
     fn on_new_data(&mut self, _time: &B::Timestamp, _data: &[B::Datum]) {}
 
-    /// Try to emit data from buffer by the given watermark
-    fn try_emit(&mut self, watermark: Watermark<B::Timestamp>) -> EmitResult<B>;
+    /// Try to emit data from buffer by the given watermark. Implementations
+    /// that support allowed lateness may return the same window time more
+    /// than once, tagged `Firing::Late`, as late data arrives for it.
+    fn try_emit(&mut self, watermark: Watermark<B::Timestamp>) -> EmitResult<B, Self::Output>;
+
+    /// Optional hook for speculative early panes, polled once per operator
+    /// activation ahead of `try_emit`. The default never fires early.
+    fn on_trigger(&mut self) -> EmitResult<B, Self::Output> {
+        None
+    }
+
+    /// Window times that have passed their lateness deadline and will never
+    /// fire again. The driving operator retains a capability per window
+    /// time so `Firing::Late` panes can still be emitted after the main
+    /// capability has advanced past it; this tells the operator when it's
+    /// safe to drop that retained capability. Default: nothing ever expires
+    /// (no lateness support, so nothing is ever retained in the first
+    /// place).
+    fn expired(&mut self) -> Vec<B::Timestamp> {
+        vec![]
+    }
+
+    /// Whether this window might still emit `Firing::Late` for a time it
+    /// has already fired `Early`/`OnTime` for. The driving operator only
+    /// retains a capability at a window's end when this is `true`; windows
+    /// that answer `false` (the default) never need one held past their
+    /// on-time firing, since `expired()` would never report it anyway.
+    /// Implementations with no lateness support whatsoever (e.g.
+    /// `SlidingWindow`, `AggregatingWindow`) must leave this `false` so the
+    /// operator's output frontier keeps advancing.
+    fn supports_late_firing(&self) -> bool {
+        false
+    }
+}
+
+/// Turn one `try_emit`/`on_trigger` result into a stashed, capability-backed
+/// output. `Early`/`OnTime` panes mint their output capability from `cap`
+/// (only `OnTime` downgrades it); `Late` panes reuse the capability retained
+/// from that window's earlier firing instead, since by the time late data
+/// arrives `cap` has usually advanced past the window's end already and
+/// `delayed`/`downgrade` to an earlier time would panic.
+///
+/// `retain` should come from `Window::supports_late_firing()`: a capability
+/// is only worth holding onto if the window can actually re-fire `Late` for
+/// `emit_time` later. Retaining unconditionally would leak one capability
+/// per firing for windows that never re-fire (nothing would ever remove
+/// it), pinning the output frontier at the first window's end forever.
+fn stash_emission<T: Timestamp, O: Data>(
+    firing: Firing,
+    emit_time: T,
+    emit_data: Vec<(T, O)>,
+    retain: bool,
+    cap: &mut Option<Capability<T>>,
+    retained: &mut HashMap<T, Capability<T>>,
+    stash: &mut HashMap<T, Vec<(T, O)>>,
+    notificator: &mut FrontierNotificator<T>,
+) {
+    let new_time = match firing {
+        Firing::Late => {
+            let source = retained
+                .get(&emit_time)
+                .cloned()
+                .unwrap_or_else(|| cap.as_ref().unwrap().clone());
+            source.delayed(&emit_time)
+        }
+        Firing::Early | Firing::OnTime => {
+            let cap = cap.as_mut().unwrap();
+            let source = cap.clone();
+            let new_time = source.delayed(&emit_time);
+            if retain {
+                retained.entry(emit_time.clone()).or_insert(source);
+            }
+            if firing == Firing::OnTime {
+                cap.downgrade(&emit_time);
+            }
+            new_time
+        }
+    };
+
+    stash
+        .entry(new_time.clone())
+        .or_insert_with(|| {
+            notificator.notify_at(new_time);
+            vec![]
+        })
+        .extend(emit_data);
 }
 
 pub trait WindowOp<G: Scope, D: Data> {
-    fn window<W: Window<B> + 'static, B: WindowBuffer<Timestamp = G::Timestamp, Datum = D>>(
+    fn window<W, B>(&self, name: &str, window: W) -> Stream<G, Vec<(G::Timestamp, W::Output)>>
+    where
+        W: Window<B> + 'static,
+        B: WindowBuffer<Timestamp = G::Timestamp, Datum = D>;
+
+    /// Like `window`, but bounds how far the input is allowed to run ahead
+    /// of a downstream `probe`. Once the gap between the input frontier and
+    /// the probed output frontier exceeds `max_outstanding`, the operator
+    /// stops pulling from its input until the probe catches back up, making
+    /// the window a point of backpressure instead of an unbounded buffer in
+    /// front of a lagging consumer.
+    fn window_flow_controlled<W, B>(
         &self,
         name: &str,
         window: W,
-    ) -> Stream<G, Vec<(G::Timestamp, D)>>;
+        probe: ProbeHandle<G::Timestamp>,
+        max_outstanding: <G::Timestamp as Timestamp>::Summary,
+    ) -> Stream<G, Vec<(G::Timestamp, W::Output)>>
+    where
+        W: Window<B> + 'static,
+        B: WindowBuffer<Timestamp = G::Timestamp, Datum = D>;
 }
 
 impl<G: Scope, D: Data> WindowOp<G, D> for Stream<G, D> {
-    fn window<W: Window<B> + 'static, B: WindowBuffer<Timestamp = G::Timestamp, Datum = D>>(
-        &self,
-        name: &str,
-        mut window: W,
-    ) -> Stream<G, Vec<(G::Timestamp, D)>> {
+    fn window<W, B>(&self, name: &str, mut window: W) -> Stream<G, Vec<(G::Timestamp, W::Output)>>
+    where
+        W: Window<B> + 'static,
+        B: WindowBuffer<Timestamp = G::Timestamp, Datum = D>,
+    {
         let mut stash = HashMap::new();
+        let mut retained = HashMap::new();
 
         self.unary_frontier(Pipeline, name, |cap, _| {
             let mut cap = Some(cap);
@@ -119,19 +255,146 @@ impl<G: Scope, D: Data> WindowOp<G, D> for Stream<G, D> {
                         window.give_vec(time.time().clone(), data.take());
                     });
 
-                    if let Some((emit_time, emit_data)) =
+                    let retain = window.supports_late_firing();
+
+                    if let Some((emit_time, firing, emit_data)) = window.on_trigger() {
+                        stash_emission(
+                            firing,
+                            emit_time,
+                            emit_data,
+                            retain,
+                            &mut cap,
+                            &mut retained,
+                            &mut stash,
+                            &mut notificator,
+                        );
+                    }
+
+                    if let Some((emit_time, firing, emit_data)) =
+                        window.try_emit(Watermark::new(input.frontier()))
+                    {
+                        stash_emission(
+                            firing,
+                            emit_time,
+                            emit_data,
+                            retain,
+                            &mut cap,
+                            &mut retained,
+                            &mut stash,
+                            &mut notificator,
+                        );
+                    }
+
+                    for expired in window.expired() {
+                        retained.remove(&expired);
+                    }
+                }
+
+                notificator.for_each(&[input.frontier()], |time, _| {
+                    if let Some(data) = stash.remove(&time) {
+                        output.session(&time).give(data);
+                    }
+                });
+
+                stash.retain(|_, v| !v.is_empty());
+            }
+        })
+    }
+
+    fn window_flow_controlled<W, B>(
+        &self,
+        name: &str,
+        mut window: W,
+        probe: ProbeHandle<G::Timestamp>,
+        max_outstanding: <G::Timestamp as Timestamp>::Summary,
+    ) -> Stream<G, Vec<(G::Timestamp, W::Output)>>
+    where
+        W: Window<B> + 'static,
+        B: WindowBuffer<Timestamp = G::Timestamp, Datum = D>,
+    {
+        let mut stash = HashMap::new();
+        let mut retained = HashMap::new();
+        let scope = self.scope();
+
+        self.unary_frontier(Pipeline, name, |cap, info| {
+            let mut cap = Some(cap);
+            let mut notificator = FrontierNotificator::new();
+            let activator = scope.activator_for(info.address);
+            // The output frontier last seen while blocked, so we can tell a
+            // stale recheck (nothing downstream has changed, don't bother
+            // re-activating) apart from real progress (something may have
+            // unblocked us, worth a look).
+            let mut blocked_on: Option<Vec<G::Timestamp>> = None;
+
+            move |input, output| {
+                if input.frontier().is_empty() {
+                    cap = None;
+                } else {
+                    let output_frontier = probe.with_frontier(|f| f.frontier().to_vec());
+                    let blocked = input.frontier().frontier().iter().any(|input_time| {
+                        output_frontier.iter().any(|output_time| {
+                            max_outstanding
+                                .results_in(output_time)
+                                .is_some_and(|limit| limit.less_than(input_time))
+                        })
+                    });
+
+                    // Only ingestion is gated by `blocked`: windows that
+                    // already hold buffered data must still be allowed to
+                    // close and downgrade the capability, or the output
+                    // frontier (and with it the probe this operator is
+                    // waiting on) could never advance, livelocking the gap
+                    // shut for good.
+                    if blocked {
+                        // Re-activating unconditionally every round would
+                        // busy-spin for as long as the probe stays put.
+                        // Only ask to be scheduled again when the probed
+                        // frontier has actually moved since we last checked
+                        // it — that's the only thing that can have changed
+                        // whether we're still blocked.
+                        if blocked_on.as_ref() != Some(&output_frontier) {
+                            activator.activate();
+                        }
+                        blocked_on = Some(output_frontier);
+                    } else {
+                        blocked_on = None;
+                        input.for_each(|time, data| {
+                            window.give_vec(time.time().clone(), data.take());
+                        });
+                    }
+
+                    let retain = window.supports_late_firing();
+
+                    if let Some((emit_time, firing, emit_data)) = window.on_trigger() {
+                        stash_emission(
+                            firing,
+                            emit_time,
+                            emit_data,
+                            retain,
+                            &mut cap,
+                            &mut retained,
+                            &mut stash,
+                            &mut notificator,
+                        );
+                    }
+
+                    if let Some((emit_time, firing, emit_data)) =
                         window.try_emit(Watermark::new(input.frontier()))
                     {
-                        let cap = cap.as_mut().unwrap();
-                        let new_time = cap.delayed(&emit_time);
-                        cap.downgrade(&emit_time);
-                        stash
-                            .entry(new_time.clone())
-                            .or_insert_with(|| {
-                                notificator.notify_at(new_time);
-                                vec![]
-                            })
-                            .extend(emit_data)
+                        stash_emission(
+                            firing,
+                            emit_time,
+                            emit_data,
+                            retain,
+                            &mut cap,
+                            &mut retained,
+                            &mut stash,
+                            &mut notificator,
+                        );
+                    }
+
+                    for expired in window.expired() {
+                        retained.remove(&expired);
                     }
                 }
 
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use timely::progress::PathSummary;
+use timely::progress::Timestamp;
+use timely::Data;
+
+use crate::generic::{EmitResult, Firing, Watermark, Window, WindowBuffer};
+
+/// A `WindowBuffer` that discards everything it is given.
+///
+/// `AggregatingWindow` folds each record into its accumulator as soon as it
+/// arrives (see `on_new_data` below), so it never needs the generic
+/// buffer/replay path `Window::give_vec` otherwise relies on. This stands in
+/// for that buffer so the window can still be driven through `WindowOp`.
+pub struct NullBuffer<T, D>(PhantomData<(T, D)>);
+
+impl<T, D> Default for NullBuffer<T, D> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Timestamp, D: Data> WindowBuffer for NullBuffer<T, D> {
+    type Timestamp = T;
+    type Datum = D;
+
+    fn timestamps(&self) -> Vec<&Self::Timestamp> {
+        vec![]
+    }
+
+    fn store(&mut self, _time: Self::Timestamp, _data: Vec<Self::Datum>) {}
+
+    fn get(&self, _time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        None
+    }
+
+    fn remove(&mut self, _time: &Self::Timestamp) -> Option<Vec<Self::Datum>> {
+        None
+    }
+}
+
+/// A tumbling window that folds incoming data into a per-window accumulator
+/// instead of buffering every raw record, trading replay-at-emit-time for a
+/// streaming aggregate (sums, counts, histograms, ...) that never retains
+/// the source data.
+pub struct AggregatingWindow<T: Timestamp, D: Data, A: Data, F: Fn(&mut A, &D) + 'static> {
+    size: T::Summary,
+    /// Fixed anchor the window grid is computed from; never mutated, so a
+    /// late record can always be walked to its true bucket from here.
+    origin: Option<T>,
+    /// End of the furthest window that a record at or past the previous
+    /// cursor has landed in. Used as the starting point for `window_end`'s
+    /// forward walk for records at or past it; records before it are
+    /// walked from `origin`.
+    cursor: Option<T>,
+    /// End of the most recently emitted window. A record whose bucket ends
+    /// at or before this has arrived after that bucket already fired; there
+    /// is no buffer to replay a late pane from (unlike `TumblingWindow`'s
+    /// `allowed_lateness`), so it is dropped rather than silently re-opening
+    /// and re-emitting a bucket that already went out.
+    last_emitted: Option<T>,
+    buckets: HashMap<T, A>,
+    init: Box<dyn Fn() -> A>,
+    fold: F,
+    buffer: NullBuffer<T, D>,
+}
+
+impl<T: Timestamp, D: Data, A: Data, F: Fn(&mut A, &D) + 'static> AggregatingWindow<T, D, A, F> {
+    pub fn new(
+        size: T::Summary,
+        init_time: Option<T>,
+        init: impl Fn() -> A + 'static,
+        fold: F,
+    ) -> Self {
+        let origin = init_time.map(|t| size.results_in(&t).unwrap());
+        Self {
+            size,
+            origin,
+            cursor: None,
+            last_emitted: None,
+            buckets: HashMap::default(),
+            init: Box::new(init),
+            fold,
+            buffer: NullBuffer::default(),
+        }
+    }
+
+    /// The end (exclusive) of the window that `time` belongs to.
+    ///
+    /// A record at or past `cursor` (the end of the furthest window so far)
+    /// walks forward from `cursor`, which this then advances to the end of
+    /// the record's window. Any record before `cursor`, which includes every
+    /// record inside the furthest window as well as late ones, is walked
+    /// from the fixed `origin` instead, so it lands in its own bucket; that
+    /// walk is linear in the number of windows between `origin` and the
+    /// record.
+    fn window_end(&mut self, time: &T) -> T {
+        let origin = self
+            .origin
+            .get_or_insert_with(|| self.size.results_in(time).unwrap())
+            .clone();
+        let cursor = self.cursor.clone().unwrap_or_else(|| origin.clone());
+
+        if !time.lt(&cursor) {
+            let mut end = cursor;
+            while !time.lt(&end) {
+                end = self.size.results_in(&end).unwrap();
+            }
+            self.cursor = Some(end.clone());
+            return end;
+        }
+
+        let mut end = origin;
+        while !time.lt(&end) {
+            end = self.size.results_in(&end).unwrap();
+        }
+        end
+    }
+}
+
+impl<T: Timestamp, D: Data, A: Data, F: Fn(&mut A, &D) + 'static> Window<NullBuffer<T, D>>
+    for AggregatingWindow<T, D, A, F>
+{
+    type Output = A;
+
+    fn buffer(&mut self) -> &mut NullBuffer<T, D> {
+        &mut self.buffer
+    }
+
+    fn on_new_data(&mut self, time: &T, data: &[D]) {
+        let end = self.window_end(time);
+        if self.last_emitted.as_ref().is_some_and(|emitted| !emitted.lt(&end)) {
+            return;
+        }
+        let acc = self.buckets.entry(end).or_insert_with(|| (self.init)());
+        for datum in data {
+            (self.fold)(acc, datum);
+        }
+    }
+
+    fn try_emit(&mut self, watermark: Watermark<T>) -> EmitResult<NullBuffer<T, D>, A> {
+        let mut ready_ends = self
+            .buckets
+            .keys()
+            .filter(|end| !watermark.less_equal(end))
+            .cloned()
+            .collect::<Vec<_>>();
+        ready_ends.sort();
+
+        let end = ready_ends.into_iter().next()?;
+        let acc = self.buckets.remove(&end).unwrap();
+        self.last_emitted = Some(end.clone());
+        Some((end.clone(), Firing::OnTime, vec![(end, acc)]))
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use timely::order::PartialOrder;
+use timely::progress::PathSummary;
+use timely::progress::Timestamp;
+use timely::Data;
+
+use crate::generic::{EmitResult, Firing, Watermark, Window, WindowBuffer};
+
+/// A hopping window: a record at time `t` belongs to every window whose
+/// `[start, start + size)` interval contains `t`. With `slide < size`
+/// windows overlap, so a record can be emitted by more than one window.
+pub struct SlidingWindow<T: Timestamp, D: Data, B: WindowBuffer<Timestamp = T, Datum = D> = HashMap<T, Vec<D>>>
+{
+    size: T::Summary,
+    slide: T::Summary,
+    /// Start of the earliest window that has not yet closed.
+    next_start: Option<T>,
+    buffer: B,
+}
+
+impl<T: Timestamp, D: Data> SlidingWindow<T, D, HashMap<T, Vec<D>>> {
+    /// `slide` must be less than `size`, or windows no longer overlap and
+    /// records landing in the gap between them are silently dropped.
+    pub fn new(size: T::Summary, slide: T::Summary, init_time: Option<T>) -> Self {
+        Self::new_with_buffer(size, slide, init_time, HashMap::default())
+    }
+}
+
+impl<T: Timestamp, D: Data, B: WindowBuffer<Timestamp = T, Datum = D>> SlidingWindow<T, D, B> {
+    /// `slide` must be less than `size`, or windows no longer overlap and
+    /// records landing in the gap between them are silently dropped.
+    pub fn new_with_buffer(size: T::Summary, slide: T::Summary, init_time: Option<T>, buffer: B) -> Self {
+        debug_assert!(
+            slide.less_than(&size),
+            "SlidingWindow requires slide < size, otherwise records landing \
+             in the gap between windows are silently dropped"
+        );
+        Self {
+            size,
+            slide,
+            next_start: init_time,
+            buffer,
+        }
+    }
+}
+
+impl<T: Timestamp, D: Data, B: WindowBuffer<Timestamp = T, Datum = D>> Window<B>
+    for SlidingWindow<T, D, B>
+{
+    type Output = D;
+
+    fn buffer(&mut self) -> &mut B {
+        &mut self.buffer
+    }
+
+    fn on_new_data(&mut self, time: &T, _data: &[D]) {
+        if self.next_start.is_none() {
+            self.next_start = Some(time.clone());
+        }
+    }
+
+    fn try_emit(&mut self, watermark: Watermark<T>) -> EmitResult<B, D> {
+        let start = self.next_start.clone()?;
+        let end = self.size.results_in(&start).unwrap();
+
+        if watermark.less_equal(&end) {
+            return None;
+        }
+
+        let mut times = self
+            .buffer
+            .timestamps()
+            .into_iter()
+            .filter(|time| !(*time).lt(&start) && (*time).lt(&end))
+            .cloned()
+            .collect::<Vec<_>>();
+        times.sort();
+
+        let mut data = vec![];
+        for time in &times {
+            if let Some(items) = self.buffer.get(time) {
+                data.extend(items.into_iter().map(|v| (time.clone(), v)));
+            }
+        }
+
+        // Advance the emit cursor by one slide: no future window can start
+        // before this, so any record that now falls before the new start is
+        // no longer needed by an open window and can be dropped.
+        let next_start = self.slide.results_in(&start).unwrap();
+        let stale = self
+            .buffer
+            .timestamps()
+            .into_iter()
+            .filter(|time| (*time).lt(&next_start))
+            .cloned()
+            .collect::<Vec<_>>();
+        for time in stale {
+            self.buffer.remove(&time);
+        }
+        self.next_start = Some(next_start);
+
+        Some((end, Firing::OnTime, data))
+    }
+}
@@ -4,46 +4,183 @@ use timely::progress::PathSummary;
 use timely::progress::Timestamp;
 use timely::Data;
 
-use crate::{Watermark, Window, WindowBuffer};
+use crate::generic::{EmitResult, Firing, Watermark, Window, WindowBuffer};
 
-pub struct TumblingWindow<T: Timestamp, D: Data> {
+pub struct TumblingWindow<T: Timestamp, D: Data, B: WindowBuffer<Timestamp = T, Datum = D> = HashMap<T, Vec<D>>>
+{
     size: T::Summary,
     emit_time: Option<T>,
-    buffer: Box<dyn WindowBuffer<T, D>>,
+    buffer: B,
+    allowed_lateness: Option<T::Summary>,
+    /// Windows that already fired on time, keyed by their end, mapped to
+    /// their `(start, deadline)`: `start` (`None` for the very first window)
+    /// bounds late data to the window's own interval, and `deadline` (end +
+    /// allowed_lateness) is the watermark past which they stop accepting
+    /// late data.
+    open_for_late: HashMap<T, (Option<T>, T)>,
+    /// Start of the currently-open window, i.e. the end of the previous one
+    /// (`None` before the first window has closed).
+    window_start: Option<T>,
+    /// Window times past their lateness deadline, handed out once via
+    /// `expired` so the operator can drop the capability it was holding for
+    /// them.
+    expired: Vec<T>,
+    /// Count-based early trigger: fire a speculative `Firing::Early` pane
+    /// once this many records have landed in the currently-open window.
+    /// There is no processing-time/wall-clock primitive in this crate, so
+    /// only count-based early triggers are supported.
+    early_trigger_count: Option<usize>,
+    /// Records seen for the currently-open window since it last fired
+    /// (on time or early).
+    pending_count: usize,
 }
 
-impl<T: Timestamp, D: Data> TumblingWindow<T, D> {
+impl<T: Timestamp, D: Data> TumblingWindow<T, D, HashMap<T, Vec<D>>> {
     pub fn new(size: T::Summary, init_time: Option<T>) -> Self {
         Self::new_with_buffer(size, init_time, HashMap::default())
     }
+}
 
-    pub fn new_with_buffer<B: WindowBuffer<T, D> + 'static>(
-        size: T::Summary,
-        init_time: Option<T>,
-        buffer: B,
-    ) -> Self {
+impl<T: Timestamp, D: Data, B: WindowBuffer<Timestamp = T, Datum = D>> TumblingWindow<T, D, B> {
+    pub fn new_with_buffer(size: T::Summary, init_time: Option<T>, buffer: B) -> Self {
         let emit_time = init_time.map(|t| size.results_in(&t).unwrap());
-        let buffer = Box::new(buffer);
         Self {
             size,
             emit_time,
             buffer,
+            allowed_lateness: None,
+            open_for_late: HashMap::new(),
+            window_start: None,
+            expired: vec![],
+            early_trigger_count: None,
+            pending_count: 0,
         }
     }
+
+    /// Accept late-arriving records for `lateness` after a window's
+    /// watermark has passed its end, re-firing (tagged `Firing::Late`) with
+    /// the newly arrived data instead of dropping it.
+    pub fn with_allowed_lateness(mut self, lateness: T::Summary) -> Self {
+        self.allowed_lateness = Some(lateness);
+        self
+    }
+
+    /// Fire a speculative `Firing::Early` pane, replaying everything seen so
+    /// far for the currently-open window, once `count` records have landed
+    /// in it since its last firing.
+    pub fn with_early_trigger(mut self, count: usize) -> Self {
+        self.early_trigger_count = Some(count);
+        self
+    }
 }
 
-impl<T: Timestamp, D: Data> Window<T, D> for TumblingWindow<T, D> {
-    fn buffer(&mut self) -> &mut dyn WindowBuffer<T, D> {
-        self.buffer.as_mut()
+impl<T: Timestamp, D: Data, B: WindowBuffer<Timestamp = T, Datum = D>> Window<B>
+    for TumblingWindow<T, D, B>
+{
+    type Output = D;
+
+    fn buffer(&mut self) -> &mut B {
+        &mut self.buffer
     }
 
-    fn on_new_data(&mut self, time: &T, _data: &Vec<D>) {
+    fn on_new_data(&mut self, time: &T, data: &[D]) {
         if self.emit_time.is_none() {
             self.emit_time = Some(self.size.results_in(time).unwrap());
         }
+        let in_current_window = self
+            .emit_time
+            .as_ref()
+            .is_some_and(|end| time.lt(end))
+            && self
+                .window_start
+                .as_ref()
+                .is_none_or(|start| !time.lt(start));
+        if in_current_window {
+            self.pending_count += data.len();
+        }
+    }
+
+    fn on_trigger(&mut self) -> EmitResult<B, D> {
+        let threshold = self.early_trigger_count?;
+        if self.pending_count < threshold {
+            return None;
+        }
+        let emit_time = self.emit_time.clone()?;
+
+        let mut times = self
+            .buffer
+            .timestamps()
+            .into_iter()
+            .filter(|time| {
+                (*time).lt(&emit_time)
+                    && self
+                        .window_start
+                        .as_ref()
+                        .is_none_or(|start| !(*time).lt(start))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        times.sort();
+
+        let mut data = vec![];
+        for time in &times {
+            if let Some(items) = self.buffer.get(time) {
+                data.extend(items.into_iter().map(|v| (time.clone(), v)));
+            }
+        }
+
+        // Reset the counter so the next early pane waits for another
+        // `threshold` records, but leave the buffered data in place: the
+        // window still needs it for its on-time (and possibly late) firing.
+        self.pending_count = 0;
+
+        Some((emit_time, Firing::Early, data))
     }
 
-    fn try_emit<'w>(&mut self, watermark: Watermark<'w, T>) -> Option<(T, Vec<(T, D)>)> {
+    fn try_emit(&mut self, watermark: Watermark<T>) -> EmitResult<B, D> {
+        // Flush late data for windows that already fired, or forget about
+        // them once the watermark passes their lateness deadline.
+        let mut newly_expired = vec![];
+        let mut late_ready = None;
+        for (end, (start, deadline)) in self.open_for_late.iter() {
+            if watermark.less_equal(deadline) {
+                let mut late_times = self
+                    .buffer
+                    .timestamps()
+                    .into_iter()
+                    .filter(|time| {
+                        (*time).lt(end) && start.as_ref().is_none_or(|start| !(*time).lt(start))
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !late_times.is_empty() {
+                    late_times.sort();
+                    late_ready = Some((end.clone(), late_times));
+                    break;
+                }
+            } else {
+                newly_expired.push(end.clone());
+            }
+        }
+        for end in &newly_expired {
+            self.open_for_late.remove(end);
+        }
+        self.expired.extend(newly_expired);
+        if let Some((end, late_times)) = late_ready {
+            let mut data = vec![];
+            for time in late_times {
+                data.extend(
+                    self.buffer
+                        .remove(&time)
+                        .unwrap()
+                        .into_iter()
+                        .map(|v| (time.clone(), v))
+                        .collect::<Vec<_>>(),
+                );
+            }
+            return Some((end, Firing::Late, data));
+        }
+
         let emit_time = self.emit_time.take()?;
 
         if watermark.less_equal(&emit_time) {
@@ -51,12 +188,15 @@ impl<T: Timestamp, D: Data> Window<T, D> for TumblingWindow<T, D> {
             return None;
         }
 
+        let start = self.window_start.clone();
         let mut ready_times = self
             .buffer
             .timestamps()
             .into_iter()
-            .filter(|time| (*time).lt(&emit_time))
-            .map(Clone::clone)
+            .filter(|time| {
+                (*time).lt(&emit_time) && start.as_ref().is_none_or(|start| !(*time).lt(start))
+            })
+            .cloned()
             .collect::<Vec<_>>();
         ready_times.sort();
 
@@ -72,9 +212,25 @@ impl<T: Timestamp, D: Data> Window<T, D> for TumblingWindow<T, D> {
             );
         }
 
-        // update next emit time
+        // open the next window
+        self.window_start = Some(emit_time.clone());
         self.emit_time = Some(self.size.results_in(&emit_time).unwrap());
-        Some((emit_time, data))
+        self.pending_count = 0;
+
+        if let Some(lateness) = &self.allowed_lateness {
+            let deadline = lateness.results_in(&emit_time).unwrap();
+            self.open_for_late.insert(emit_time.clone(), (start, deadline));
+        }
+
+        Some((emit_time, Firing::OnTime, data))
+    }
+
+    fn expired(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.expired)
+    }
+
+    fn supports_late_firing(&self) -> bool {
+        self.allowed_lateness.is_some()
     }
 }
 